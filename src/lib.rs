@@ -26,6 +26,11 @@
 //!
 //! ```
 
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
 use std::io::Write;
 
 /// An SSE [message](https://www.w3.org/TR/2012/WD-eventsource-20120426).
@@ -51,10 +56,41 @@ impl<W: Write> SseMessage<W> {
     ///
     /// This field is the only "required" one, in that a message with an empty data field
     /// won't trigger any event listener in the browser.
+    ///
+    /// The written value must not contain any `\n` newline characters. Use
+    /// [`data_multiline`](#method.data_multiline) if the value may contain embedded
+    /// newlines.
     pub fn data(&mut self) -> std::io::Result<SseField<&mut W>> {
         SseField::new(&mut self.0, "data")
     }
 
+    /// Append a data field, splitting any embedded newlines across multiple `data:`
+    /// lines.
+    ///
+    /// The SSE format allows a logical data payload to span multiple lines by repeating
+    /// the `data:` prefix on each line, and the browser concatenates them back together
+    /// (joined by `\n`) before delivering the payload to the event listener. This lets
+    /// values like pretty-printed JSON or arbitrary user text be written without the
+    /// caller having to pre-split them on `\n`.
+    pub fn data_multiline(&mut self) -> std::io::Result<MultilineDataField<&mut W>> {
+        MultilineDataField::new(&mut self.0)
+    }
+
+    /// Append a data field, serializing `value` as JSON directly into it.
+    ///
+    /// This streams the serialized bytes straight into the underlying `Write` (through
+    /// the newline-splitting [`data_multiline`](#method.data_multiline) field, so any
+    /// `\n` the serializer emits is handled automatically), without first formatting
+    /// `value` into an intermediate buffer.
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn json_data<T: serde::Serialize>(&mut self, value: &T) -> std::io::Result<()> {
+        let mut field = try!(self.data_multiline());
+        try!(serde_json::to_writer(&mut field, value));
+        Ok(())
+    }
+
     /// Append an event name field.
     ///
     /// This optional field tags the current message with an event name, which causes the
@@ -78,6 +114,24 @@ impl<W: Write> SseMessage<W> {
     pub fn retry(&mut self) -> std::io::Result<SseField<&mut W>> {
         SseField::new(&mut self.0, "retry")
     }
+
+    /// Append a retry field, computed from a `Duration`.
+    ///
+    /// This writes the given duration's whole millisecond count into the field,
+    /// guaranteeing a valid integer value without the caller having to format one
+    /// themselves.
+    pub fn retry_after(&mut self, d: std::time::Duration) -> std::io::Result<()> {
+        write!(try!(self.retry()), "{}", d.as_millis())
+    }
+
+    /// Append a comment.
+    ///
+    /// This is ignored by the browser and has no effect on the message. It's useful for
+    /// padding out an otherwise idle event stream to keep the underlying connection from
+    /// being closed by an intermediate proxy.
+    pub fn comment(&mut self) -> std::io::Result<SseField<&mut W>> {
+        SseField::new(&mut self.0, "")
+    }
 }
 
 /// Writes the message terminating sequence and flushes on drop.
@@ -117,6 +171,43 @@ impl<W: Write> Drop for SseField<W> {
     fn drop(&mut self) { self.0.write(b"\n").is_ok(); }
 }
 
+/// A `data` field in an SSE message which automatically reformats embedded newlines.
+///
+/// This behaves like [`SseField`](struct.SseField.html), except any `\n` written into
+/// it terminates the current `data:` line and begins a fresh one, instead of producing
+/// a corrupt or early-terminated message.
+pub struct MultilineDataField<W: Write>(W);
+
+impl<W: Write> MultilineDataField<W> {
+    /// Create a new `MultilineDataField` to write into the given stream.
+    fn new(mut stream: W) -> std::io::Result<Self> {
+        try!(write!(&mut stream, "data:"));
+        Ok(MultilineDataField(stream))
+    }
+}
+
+/// Appends to the value of the current field, splitting on newlines.
+impl<W: Write> Write for MultilineDataField<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for (i, line) in buf.split(|&b| b == b'\n').enumerate() {
+            if i > 0 {
+                try!(self.0.write_all(b"\ndata:"));
+            }
+
+            try!(self.0.write_all(line));
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { self.0.flush() }
+}
+
+/// Writes the field terminating sequence on drop.
+impl<W: Write> Drop for MultilineDataField<W> {
+    fn drop(&mut self) { self.0.write(b"\n").is_ok(); }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -136,6 +227,60 @@ mod test {
         assert_eq!(&buf[..], &b"hello:a message 1337 another message\n"[..]);
     }
 
+    #[test]
+    fn test_sse_comment() {
+        let mut buf = [0u8; 12];
+
+        {
+            let mut msg = SseMessage::new(&mut buf[..]);
+            write!(msg.comment().unwrap(), "keepalive").unwrap();
+        }
+
+        assert_eq!(&buf[..], &b":keepalive\n\n"[..]);
+    }
+
+    #[test]
+    fn test_sse_data_multiline() {
+        let mut buf = [0u8; 29];
+
+        {
+            let mut msg = SseMessage::new(&mut buf[..]);
+            write!(msg.data_multiline().unwrap(), "line one\nline two").unwrap();
+        }
+
+        assert_eq!(&buf[..], &b"data:line one\ndata:line two\n\n"[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_sse_json_data() {
+        #[derive(serde::Serialize)]
+        struct Msg<'a> {
+            text: &'a str,
+        }
+
+        let mut buf = [0u8; 21];
+
+        {
+            let mut msg = SseMessage::new(&mut buf[..]);
+            msg.json_data(&Msg { text: "abc" }).unwrap();
+        }
+
+        assert_eq!(&buf[..], &b"data:{\"text\":\"abc\"}\n\n"[..]);
+    }
+
+    #[test]
+    fn test_sse_retry_after() {
+        let mut buf = [0u8; 12];
+
+        {
+            let mut msg = SseMessage::new(&mut buf[..]);
+            msg.retry_after(std::time::Duration::from_secs(5)).unwrap();
+        }
+
+        assert_eq!(&buf[..], &b"retry:5000\n\n"[..]);
+    }
+
     #[test]
     fn test_sse_msg() {
         let mut buf = [0u8; 44];